@@ -0,0 +1,131 @@
+// Copyright (c) 2018 Pietro Albini <pietro@pietroalbini.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is furnished to do
+// so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::data::{Backend, Repo};
+use crate::prelude::*;
+use crate::sink::Sink;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+    last_id: HashMap<String, usize>,
+}
+
+/// Stores repositories by appending rows to per-platform `.csv` objects, and
+/// tracks the scraping cursor in a `state.json` object next to them, both
+/// written through a [`Sink`] rather than directly to the local disk.
+///
+/// This is the original, append-only storage format: re-running the scraper
+/// over a range it already covered will duplicate rows rather than upsert
+/// them, unlike [`SqliteBackend`](super::sqlite::SqliteBackend).
+pub struct CsvBackend {
+    sink: Arc<dyn Sink>,
+
+    csv_write_lock: Arc<Mutex<()>>,
+    state_cache: Arc<Mutex<Option<State>>>,
+}
+
+impl CsvBackend {
+    pub fn new(sink: Arc<dyn Sink>) -> Self {
+        CsvBackend {
+            sink,
+
+            csv_write_lock: Arc::new(Mutex::new(())),
+            state_cache: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    async fn edit_state<T, F: Fn(&mut State) -> Fallible<T>>(&self, f: F) -> Fallible<T> {
+        let mut state_cache = self.state_cache.lock().await;
+
+        if state_cache.is_none() {
+            *state_cache = match self.sink.read("state.json").await? {
+                Some(contents) => Some(serde_json::from_slice(&contents)?),
+                None => Some(Default::default()),
+            };
+        }
+
+        let state = state_cache.as_mut().unwrap();
+        let result = f(state)?;
+
+        let mut contents = serde_json::to_vec_pretty(&state)?;
+        contents.push(b'\n');
+        self.sink.write("state.json", &contents).await?;
+
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl Backend for CsvBackend {
+    async fn get_last_id(&self, platform: &str) -> Fallible<Option<usize>> {
+        self.edit_state(|state| Ok(state.last_id.get(platform).cloned()))
+            .await
+    }
+
+    async fn set_last_id(&self, platform: &str, id: usize) -> Fallible<()> {
+        self.edit_state(|state| {
+            state.last_id.insert(platform.to_string(), id);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn store_repo(&self, platform: &str, repo: Repo) -> Fallible<()> {
+        // Ensure only one caller can write to a platform's CSV object at once
+        let _lock = self.csv_write_lock.lock().await;
+
+        let key = format!("{}.csv", platform);
+        let write_headers = !self.sink.exists(&key).await?;
+
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(write_headers)
+            .from_writer(Vec::new());
+        writer.serialize(repo)?;
+        let row = writer.into_inner().map_err(|err| err_msg(err.to_string()))?;
+
+        self.sink.append(&key, &row).await
+    }
+
+    async fn recent_repos(&self, platform: &str, limit: usize) -> Fallible<Vec<Repo>> {
+        let key = format!("{}.csv", platform);
+        let contents = match self.sink.read(&key).await? {
+            Some(contents) => contents,
+            None => return Ok(Vec::new()),
+        };
+
+        // The first row written to a fresh object is a header row (see
+        // `store_repo`), so it must be skipped here rather than deserialized
+        // as a `Repo`.
+        let mut repos = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(contents.as_slice())
+            .into_deserialize::<Repo>()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        repos.reverse();
+        repos.retain(|repo| repo.has_cargo_toml);
+        repos.truncate(limit);
+        Ok(repos)
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright (c) 2018 Pietro Albini <pietro@pietroalbini.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is furnished to do
+// so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod csv;
+mod sqlite;
+
+use crate::config::Config;
+use crate::feed;
+use crate::prelude::*;
+use crate::sink::{self, Sink};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+pub use self::csv::CsvBackend;
+pub use self::sqlite::SqliteBackend;
+
+/// Number of recent repositories included in a platform's Atom feed.
+const FEED_ENTRIES: usize = 50;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Repo {
+    pub id: String,
+    pub name: String,
+    pub has_cargo_toml: bool,
+    pub has_cargo_lock: bool,
+    pub discovered_at: DateTime<Utc>,
+}
+
+/// Which storage implementation [`Data`] should persist repositories and
+/// scraping progress to. Selected in `Config`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    Csv,
+    Sqlite,
+}
+
+#[async_trait]
+trait Backend: Send + Sync {
+    async fn store_repo(&self, platform: &str, repo: Repo) -> Fallible<()>;
+    async fn get_last_id(&self, platform: &str) -> Fallible<Option<usize>>;
+    async fn set_last_id(&self, platform: &str, id: usize) -> Fallible<()>;
+    /// Returns up to `limit` of the most recently stored repositories for
+    /// `platform` that carry a `Cargo.toml`, newest first. The `has_cargo_toml`
+    /// filter is applied before `limit`, so the result is the newest `limit`
+    /// Cargo repos, not whatever happens to survive truncating an unfiltered
+    /// page of `limit` repos of any kind.
+    async fn recent_repos(&self, platform: &str, limit: usize) -> Fallible<Vec<Repo>>;
+}
+
+/// Persists scraped repositories and per-platform scraping progress.
+///
+/// Storage is delegated to a [`Backend`] implementation chosen by
+/// `Config::storage_backend`, so callers don't need to care whether rows end
+/// up in CSV files or a SQLite database.
+pub struct Data {
+    sink: Arc<dyn Sink>,
+    backend: Box<dyn Backend>,
+}
+
+impl Data {
+    pub fn new(config: &Config) -> Fallible<Self> {
+        let sink = sink::build(config)?;
+
+        let backend: Box<dyn Backend> = match config.storage_backend {
+            StorageBackend::Csv => Box::new(CsvBackend::new(sink.clone())),
+            // The SQLite backend needs a real file to open a connection
+            // against, so it keeps talking to `config.data_dir` directly
+            // rather than going through a `Sink`.
+            StorageBackend::Sqlite => Box::new(SqliteBackend::new(config)?),
+        };
+
+        Ok(Data { sink, backend })
+    }
+
+    pub async fn get_last_id(&self, platform: &str) -> Fallible<Option<usize>> {
+        self.backend.get_last_id(platform).await
+    }
+
+    pub async fn set_last_id(&self, platform: &str, id: usize) -> Fallible<()> {
+        self.backend.set_last_id(platform, id).await?;
+        crate::metrics::LAST_ID
+            .with_label_values(&[platform])
+            .set(id as i64);
+        Ok(())
+    }
+
+    pub async fn store_repo(&self, platform: &str, repo: Repo) -> Fallible<()> {
+        crate::metrics::REPOS_STORED
+            .with_label_values(&[platform])
+            .inc();
+        if repo.has_cargo_toml {
+            crate::metrics::REPOS_WITH_CARGO_TOML
+                .with_label_values(&[platform])
+                .inc();
+        }
+        if repo.has_cargo_lock {
+            crate::metrics::REPOS_WITH_CARGO_LOCK
+                .with_label_values(&[platform])
+                .inc();
+        }
+
+        self.backend.store_repo(platform, repo).await
+    }
+
+    /// Writes an Atom feed of the most recently discovered Cargo repositories
+    /// for `platform` to the `<platform>.atom` object.
+    pub async fn write_feed(&self, platform: &str) -> Fallible<()> {
+        let repos = self.backend.recent_repos(platform, FEED_ENTRIES).await?;
+        let feed = feed::build_feed(platform, &repos);
+        self.sink
+            .write(&format!("{}.atom", platform), &feed::feed_to_bytes(&feed)?)
+            .await
+    }
+}
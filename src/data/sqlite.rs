@@ -0,0 +1,174 @@
+// Copyright (c) 2018 Pietro Albini <pietro@pietroalbini.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is furnished to do
+// so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::config::Config;
+use crate::data::{Backend, Repo};
+use crate::prelude::*;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::{Arc, Mutex};
+use tokio::task;
+
+/// Stores repositories and scraping progress in a single SQLite database
+/// instead of per-platform CSV files plus a `state.json`.
+///
+/// Rows are upserted on `(platform, id)`, so re-running the scraper over a
+/// range it already covered does not duplicate data, and `last_id` updates
+/// are a single row write rather than a full rewrite of a JSON file.
+///
+/// `rusqlite` is synchronous, so every query below runs inside
+/// `spawn_blocking` rather than directly on the async `Backend` method —
+/// otherwise a slow query would block the Tokio worker thread it landed on.
+pub struct SqliteBackend {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteBackend {
+    pub fn new(config: &Config) -> Fallible<Self> {
+        let conn = Connection::open(config.data_dir.join("rust-repos.db"))?;
+
+        conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS repos (
+                platform        TEXT NOT NULL,
+                id              TEXT NOT NULL,
+                name            TEXT NOT NULL,
+                has_cargo_toml  INTEGER NOT NULL,
+                has_cargo_lock  INTEGER NOT NULL,
+                discovered_at   TEXT NOT NULL,
+                PRIMARY KEY (platform, id)
+            );
+
+            CREATE TABLE IF NOT EXISTS state (
+                platform  TEXT PRIMARY KEY,
+                last_id   INTEGER NOT NULL
+            );
+            ",
+        )?;
+
+        Ok(SqliteBackend {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Runs `f` against the connection on a blocking-pool thread, so the
+    /// synchronous `rusqlite` call it makes never blocks a Tokio worker.
+    async fn blocking<T, F>(&self, f: F) -> Fallible<T>
+    where
+        F: FnOnce(&Connection) -> Fallible<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let conn = self.conn.clone();
+        task::spawn_blocking(move || f(&conn.lock().unwrap()))
+            .await
+            .map_err(|err| err_msg(err.to_string()).context("sqlite worker thread panicked"))?
+    }
+}
+
+#[async_trait]
+impl Backend for SqliteBackend {
+    async fn get_last_id(&self, platform: &str) -> Fallible<Option<usize>> {
+        let platform = platform.to_string();
+        self.blocking(move |conn| {
+            let last_id = conn
+                .query_row(
+                    "SELECT last_id FROM state WHERE platform = ?1",
+                    params![platform],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?;
+
+            Ok(last_id.map(|id| id as usize))
+        })
+        .await
+    }
+
+    async fn set_last_id(&self, platform: &str, id: usize) -> Fallible<()> {
+        let platform = platform.to_string();
+        self.blocking(move |conn| {
+            conn.execute(
+                "INSERT INTO state (platform, last_id) VALUES (?1, ?2)
+                 ON CONFLICT (platform) DO UPDATE SET last_id = excluded.last_id",
+                params![platform, id as i64],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn store_repo(&self, platform: &str, repo: Repo) -> Fallible<()> {
+        let platform = platform.to_string();
+        self.blocking(move |conn| {
+            conn.execute(
+                "INSERT INTO repos (platform, id, name, has_cargo_toml, has_cargo_lock, discovered_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT (platform, id) DO UPDATE SET
+                    name = excluded.name,
+                    has_cargo_toml = excluded.has_cargo_toml,
+                    has_cargo_lock = excluded.has_cargo_lock,
+                    discovered_at = excluded.discovered_at",
+                params![
+                    platform,
+                    repo.id,
+                    repo.name,
+                    repo.has_cargo_toml,
+                    repo.has_cargo_lock,
+                    repo.discovered_at.to_rfc3339(),
+                ],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn recent_repos(&self, platform: &str, limit: usize) -> Fallible<Vec<Repo>> {
+        let platform = platform.to_string();
+        self.blocking(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, name, has_cargo_toml, has_cargo_lock, discovered_at
+                 FROM repos
+                 WHERE platform = ?1 AND has_cargo_toml
+                 ORDER BY discovered_at DESC
+                 LIMIT ?2",
+            )?;
+
+            let repos = stmt
+                .query_map(params![platform, limit as i64], |row| {
+                    let discovered_at: String = row.get(4)?;
+                    Ok(Repo {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        has_cargo_toml: row.get(2)?,
+                        has_cargo_lock: row.get(3)?,
+                        discovered_at: DateTime::parse_from_rfc3339(&discovered_at)
+                            .unwrap_or_else(|_| Utc::now().into())
+                            .with_timezone(&Utc),
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(repos)
+        })
+        .await
+    }
+}
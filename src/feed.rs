@@ -0,0 +1,73 @@
+// Copyright (c) 2018 Pietro Albini <pietro@pietroalbini.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is furnished to do
+// so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Turns freshly stored repositories into an Atom syndication feed, so
+//! downstream tooling can subscribe to "new Rust projects on platform X"
+//! instead of diffing CSV files or the SQLite database by hand.
+
+use crate::data::Repo;
+use crate::prelude::*;
+use atom_syndication::{EntryBuilder, FeedBuilder, LinkBuilder};
+
+/// Maps a platform identifier (e.g. `github`) to the host that serves its
+/// repositories, falling back to the identifier itself for platforms we
+/// don't special-case.
+fn platform_host(platform: &str) -> &str {
+    match platform {
+        "github" => "github.com",
+        "gitlab" => "gitlab.com",
+        "bitbucket" => "bitbucket.org",
+        other => other,
+    }
+}
+
+/// Builds an Atom feed out of `repos`, which `Data::write_feed` already
+/// narrows down to the most recently discovered repositories for `platform`
+/// that carry a `Cargo.toml`.
+pub fn build_feed(platform: &str, repos: &[Repo]) -> atom_syndication::Feed {
+    let host = platform_host(platform);
+    let entries = repos
+        .iter()
+        .map(|repo| {
+            let link = format!("https://{}/{}", host, repo.name);
+            EntryBuilder::default()
+                .title(repo.name.clone())
+                .id(link.clone())
+                .link(LinkBuilder::default().href(link).build())
+                .updated(repo.discovered_at.into())
+                .build()
+        })
+        .collect::<Vec<_>>();
+
+    FeedBuilder::default()
+        .title(format!("New Cargo repositories on {}", platform))
+        .id(format!("https://{}/", host))
+        .entries(entries)
+        .build()
+}
+
+/// Serializes `feed` as the bytes of an `.atom` document.
+pub fn feed_to_bytes(feed: &atom_syndication::Feed) -> Fallible<Vec<u8>> {
+    let mut bytes = Vec::new();
+    feed.write_to(&mut bytes)
+        .map_err(|err| err_msg(err.to_string()))
+        .context("failed to serialize atom feed")?;
+    Ok(bytes)
+}
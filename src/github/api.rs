@@ -20,15 +20,18 @@
 
 use crate::config::Config;
 use crate::prelude::*;
-use reqwest::blocking::{Client, RequestBuilder, Response};
-use reqwest::{header, Method, StatusCode};
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use reqwest::{header, Client, Method, RequestBuilder, Response, StatusCode};
 use serde::{de::DeserializeOwned, Serialize};
 use std::borrow::Cow;
+use std::future::Future;
 use std::sync::{
     atomic::{AtomicBool, AtomicUsize, Ordering},
-    Arc,
+    Arc, Mutex,
 };
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
 
 static USER_AGENT: &str = "rust-repos (https://github.com/rust-ops/rust-repos)";
 
@@ -51,6 +54,9 @@ query($ids: [ID!]!) {
 
     rateLimit {
         cost
+        remaining
+        limit
+        resetAt
     }
 }
 ";
@@ -78,24 +84,120 @@ impl ResponseExt for Response {
     }
 }
 
-pub struct GitHubApi<'conf> {
-    config: &'conf Config,
+#[derive(Debug, Clone, Copy)]
+struct RateLimitBudget {
+    remaining: u32,
+    reset_at: DateTime<Utc>,
+    // The instant the next call is allowed to go out. Every paced call (one
+    // made while the window is still open and the budget is above the
+    // safety margin) advances this under the lock by its own share of the
+    // window before releasing it, so those callers are handed distinct,
+    // increasing slots instead of all waking up from the same sleep at once.
+    next_slot: Instant,
+}
+
+impl Default for RateLimitBudget {
+    fn default() -> Self {
+        // Assume the full budget is available until the first GraphQL
+        // response tells us otherwise.
+        RateLimitBudget {
+            remaining: u32::MAX,
+            reset_at: Utc::now(),
+            next_slot: Instant::now(),
+        }
+    }
+}
+
+/// Paces GraphQL calls against GitHub's rate-limit window instead of only
+/// reacting to abuse-detection errors after the fact.
+///
+/// Every `GitHubApi` clone shares the same scheduler (it lives behind an
+/// `Arc`), so the whole process respects a single budget no matter how many
+/// concurrent callers are making requests: while the budget is above the
+/// safety margin, each call claims its slot under the lock before it starts
+/// waiting, so the shared pace is what actually serializes, not just the
+/// remaining/reset_at snapshot. Below the margin every caller sleeps until
+/// the window resets, and once `reset_at` has passed (including before the
+/// first GraphQL response is observed) calls go out without any wait.
+struct RateLimiter {
+    budget: Mutex<RateLimitBudget>,
+}
+
+impl RateLimiter {
+    /// Stop spending budget once fewer than this many points are left, and
+    /// wait out the rest of the window instead.
+    const SAFETY_MARGIN: u32 = 50;
+
+    fn new() -> Self {
+        RateLimiter {
+            budget: Mutex::new(RateLimitBudget::default()),
+        }
+    }
+
+    fn observe(&self, remaining: u32, reset_at: DateTime<Utc>) {
+        let mut budget = self.budget.lock().unwrap();
+        budget.remaining = remaining;
+        budget.reset_at = reset_at;
+    }
+
+    async fn wait_for_budget(&self) {
+        let sleep_until = {
+            let mut budget = self.budget.lock().unwrap();
+
+            let until_reset = match (budget.reset_at - Utc::now()).to_std() {
+                Ok(duration) => duration,
+                // The window already reset; there's nothing to wait for.
+                Err(_) => return,
+            };
+
+            if budget.remaining < Self::SAFETY_MARGIN {
+                warn!(
+                    "GraphQL rate-limit budget nearly exhausted ({} points left), \
+                     sleeping {}s until it resets",
+                    budget.remaining,
+                    until_reset.as_secs()
+                );
+                Instant::now() + until_reset
+            } else {
+                // Claim the next slot and advance the shared cursor by this
+                // call's share of the window, so the budget lasts until
+                // reset even when many callers are racing for a slot.
+                let slot_width = until_reset / budget.remaining;
+                let slot = budget.next_slot.max(Instant::now());
+                budget.next_slot = slot + slot_width;
+                slot
+            }
+        };
+
+        sleep(sleep_until.saturating_duration_since(Instant::now())).await;
+    }
+}
+
+#[derive(Clone)]
+pub struct GitHubApi {
+    config: Arc<Config>,
     client: Client,
     slow_down: Arc<AtomicBool>,
     concurrent_requests: Arc<AtomicUsize>,
+    rate_limiter: Arc<RateLimiter>,
 }
 
-impl<'conf> GitHubApi<'conf> {
-    pub fn new(config: &'conf Config) -> Self {
+impl GitHubApi {
+    pub fn new(config: Arc<Config>) -> Self {
         GitHubApi {
             config,
             client: Client::new(),
             slow_down: Arc::new(AtomicBool::new(false)),
             concurrent_requests: Arc::new(AtomicUsize::new(0)),
+            rate_limiter: Arc::new(RateLimiter::new()),
         }
     }
 
-    fn retry<T, F: Fn() -> Fallible<T>>(&self, f: F) -> Fallible<T> {
+    async fn retry<T, Fut, F>(&self, endpoint: &str, f: F) -> Fallible<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Fallible<T>>,
+    {
         let mut wait = Duration::from_secs(10);
         let mut first = true;
 
@@ -105,8 +207,14 @@ impl<'conf> GitHubApi<'conf> {
                 "currently making {} concurrent requests to the GitHub API",
                 concurrent + 1
             );
-            let res = f();
-            self.concurrent_requests.fetch_sub(1, Ordering::SeqCst);
+            crate::metrics::GITHUB_CONCURRENT_REQUESTS.set(concurrent as i64 + 1);
+            crate::metrics::GITHUB_API_CALLS
+                .with_label_values(&[endpoint])
+                .inc();
+
+            let res = f().await;
+            let concurrent = self.concurrent_requests.fetch_sub(1, Ordering::SeqCst);
+            crate::metrics::GITHUB_CONCURRENT_REQUESTS.set(concurrent as i64 - 1);
 
             match res {
                 Ok(res) => return Ok(res),
@@ -143,12 +251,18 @@ impl<'conf> GitHubApi<'conf> {
                 }
             }
 
+            crate::metrics::GITHUB_API_RETRIES
+                .with_label_values(&[endpoint])
+                .inc();
+
             // Slow down only once per API call
             if first {
                 self.slow_down.store(true, Ordering::SeqCst);
             }
 
-            ::std::thread::sleep(wait);
+            // Sleeping asynchronously means a retrying call no longer parks a whole
+            // thread for (potentially) minutes at a time.
+            sleep(wait).await;
 
             // Stop doubling the time after a few increments, to avoid waiting too long
             // This is still a request every ~10 minutes
@@ -176,17 +290,23 @@ impl<'conf> GitHubApi<'conf> {
             .header(header::USER_AGENT, USER_AGENT)
     }
 
-    fn graphql<T: DeserializeOwned, V: Serialize>(&self, query: &str, variables: V) -> Fallible<T> {
-        self.retry(|| {
+    async fn graphql<T: DeserializeOwned, V: Serialize>(
+        &self,
+        query: &str,
+        variables: V,
+    ) -> Fallible<T> {
+        self.retry("graphql", || async {
             let resp: GraphResponse<T> = self
                 .build_request(Method::POST, "graphql")
                 .json(&json!({
                     "query": query,
-                    "variables": variables,
+                    "variables": &variables,
                 }))
-                .send()?
+                .send()
+                .await?
                 .handle_errors()?
-                .json()?;
+                .json()
+                .await?;
 
             if let Some(data) = resp.data {
                 if let Some(errors) = resp.errors {
@@ -220,20 +340,22 @@ impl<'conf> GitHubApi<'conf> {
                 Err(err_msg("empty GraphQL response"))
             }
         })
+        .await
     }
 
-    pub fn scrape_repositories(&self, since: usize) -> Fallible<Vec<Option<RestRepository>>> {
-        self.retry(|| {
+    pub async fn scrape_repositories(&self, since: usize) -> Fallible<Vec<Option<RestRepository>>> {
+        self.retry("repositories", || async {
             let resp = self
                 .build_request(Method::GET, &format!("repositories?since={}", since))
-                .send()?
+                .send()
+                .await?
                 .handle_errors()?;
 
             let status = resp.status();
             if status == StatusCode::OK {
-                Ok(resp.json()?)
+                Ok(resp.json().await?)
             } else {
-                let error: GitHubError = resp.json()?;
+                let error: GitHubError = resp.json().await?;
                 if error.message.contains("abuse") {
                     warn!("triggered GitHub abuse detection systems");
                     Err(RetryRequest(StatusCode::TOO_MANY_REQUESTS).into())
@@ -251,24 +373,71 @@ impl<'conf> GitHubApi<'conf> {
                 }
             }
         })
+        .await
+    }
+
+    /// Lazily walk the public repository timeline starting at `since`, yielding one
+    /// item per repository instead of forcing callers to page through
+    /// `scrape_repositories` themselves. Callers can drive bounded concurrency over
+    /// the resulting stream (e.g. with `buffer_unordered`) instead of being
+    /// serialized behind a single in-flight request.
+    pub fn repositories_stream(
+        &self,
+        since: usize,
+    ) -> impl Stream<Item = Fallible<RestRepository>> + '_ {
+        futures::stream::unfold(Some(since), move |state| async move {
+            let since = state?;
+            let page = match self.scrape_repositories(since).await {
+                Ok(page) => page,
+                Err(err) => return Some((vec![Err(err)], None)),
+            };
+
+            // An empty page means we've reached the end of the public
+            // timeline. A page whose entries all happened to flatten away
+            // just means none of them were usable, not that pagination is
+            // over, so `since` must still advance past it.
+            if page.is_empty() {
+                return None;
+            }
+
+            let next = page
+                .iter()
+                .filter_map(|repo| repo.as_ref().map(|repo| repo.id))
+                .max()
+                .unwrap_or(since + page.len());
+
+            let repos: Vec<RestRepository> = page.into_iter().flatten().collect();
+            Some((repos.into_iter().map(Ok).collect::<Vec<_>>(), Some(next)))
+        })
+        .flat_map(futures::stream::iter)
     }
 
-    pub fn load_repositories(&self, node_ids: &[String]) -> Fallible<Vec<Option<GraphRepository>>> {
-        let data: GraphRepositories = self.graphql(
-            GRAPHQL_QUERY_REPOSITORIES,
-            json!({
-                "ids": node_ids,
-            }),
-        )?;
+    pub async fn load_repositories(
+        &self,
+        node_ids: &[String],
+    ) -> Fallible<Vec<Option<GraphRepository>>> {
+        self.rate_limiter.wait_for_budget().await;
+
+        let data: GraphRepositories = self
+            .graphql(
+                GRAPHQL_QUERY_REPOSITORIES,
+                json!({
+                    "ids": node_ids,
+                }),
+            )
+            .await?;
 
         assert!(
             data.rate_limit.cost <= 1,
             "load repositories query too costly"
         );
+        self.rate_limiter
+            .observe(data.rate_limit.remaining, data.rate_limit.reset_at);
+
         Ok(data.nodes)
     }
 
-    pub fn file_exists(&self, repo: &GraphRepository, path: &str) -> Fallible<bool> {
+    pub async fn file_exists(&self, repo: &GraphRepository, path: &str) -> Fallible<bool> {
         let url = format!(
             "https://raw.githubusercontent.com/{}/{}/{}",
             repo.name_with_owner,
@@ -280,10 +449,11 @@ impl<'conf> GitHubApi<'conf> {
             path,
         );
 
-        self.retry(|| {
+        self.retry("file_exists", || async {
             let resp = self
                 .build_request(Method::GET, &url)
-                .send()?
+                .send()
+                .await?
                 .handle_errors()?;
             match resp.status() {
                 StatusCode::OK => Ok(true),
@@ -298,6 +468,7 @@ impl<'conf> GitHubApi<'conf> {
                 ),
             }
         })
+        .await
     }
 
     pub fn should_slow_down(&self) -> bool {
@@ -328,8 +499,13 @@ struct GraphResponse<T> {
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GraphRateLimit {
     cost: u16,
+    remaining: u32,
+    #[allow(dead_code)]
+    limit: u32,
+    reset_at: DateTime<Utc>,
 }
 
 #[derive(Deserialize)]
@@ -0,0 +1,190 @@
+// Copyright (c) 2018 Pietro Albini <pietro@pietroalbini.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is furnished to do
+// so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Event-driven incremental discovery.
+//!
+//! `scrape_repositories` only ever walks the public timeline from a stored
+//! `since` cursor, which is slow to surface repositories right after they're
+//! created. This module adds a faster path: accept GitHub webhook
+//! deliveries (`push`/`create` events) on a small HTTP listener, verify them
+//! against a shared secret the same way the gitea_pages webhook flow does,
+//! and feed the repository they reference straight into
+//! `load_repositories`/`file_exists` instead of waiting for the next sweep.
+
+use crate::config::Config;
+use crate::data::{Data, Repo};
+use crate::github::api::GitHubApi;
+use crate::prelude::*;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{body::to_bytes, Body, Method, Request, Response, Server, StatusCode};
+use sha2::Sha256;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Deserialize)]
+struct WebhookPayload {
+    repository: WebhookRepository,
+}
+
+#[derive(Deserialize)]
+struct WebhookRepository {
+    node_id: String,
+}
+
+/// Checks a delivery's `X-Hub-Signature-256` header against `secret`.
+fn verify_signature(secret: &str, body: &[u8], signature_header: &str) -> bool {
+    let signature = match signature_header.strip_prefix("sha256=") {
+        Some(signature) => signature,
+        None => return false,
+    };
+
+    let expected = match hex::decode(signature) {
+        Ok(bytes) => bytes,
+        Err(_) => return false,
+    };
+
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts a key of any length");
+    mac.update(body);
+
+    mac.finalize().into_bytes().ct_eq(&expected).into()
+}
+
+/// Looks up the repository a single event refers to and stores it, exactly
+/// like the `since`-polling path does for each page of `scrape_repositories`.
+async fn ingest_node_id(
+    github: &GitHubApi,
+    data: &Data,
+    platform: &str,
+    node_id: String,
+) -> Fallible<()> {
+    for repo in github.load_repositories(&[node_id]).await?.into_iter().flatten() {
+        let has_cargo_toml = github.file_exists(&repo, "Cargo.toml").await?;
+        let has_cargo_lock = has_cargo_toml && github.file_exists(&repo, "Cargo.lock").await?;
+
+        data.store_repo(
+            platform,
+            Repo {
+                id: repo.id,
+                name: repo.name_with_owner,
+                has_cargo_toml,
+                has_cargo_lock,
+                discovered_at: Utc::now(),
+            },
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn handle_delivery(
+    req: Request<Body>,
+    config: &Config,
+    github: &GitHubApi,
+    data: &Data,
+    platform: &str,
+) -> Fallible<Response<Body>> {
+    if req.method() != Method::POST {
+        return Ok(Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .body(Body::empty())
+            .unwrap());
+    }
+
+    let signature = req
+        .headers()
+        .get("X-Hub-Signature-256")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+
+    let body = to_bytes(req.into_body()).await?;
+
+    match signature {
+        Some(signature) if verify_signature(&config.webhook_secret, &body, &signature) => {}
+        _ => {
+            warn!("rejected webhook delivery with a missing or invalid signature");
+            return Ok(Response::builder()
+                .status(StatusCode::UNAUTHORIZED)
+                .body(Body::empty())
+                .unwrap());
+        }
+    }
+
+    let payload: WebhookPayload = serde_json::from_slice(&body)?;
+    ingest_node_id(github, data, platform, payload.repository.node_id).await?;
+
+    Ok(Response::new(Body::empty()))
+}
+
+/// Runs an HTTP listener that accepts GitHub webhook deliveries for
+/// `platform` and keeps storing the repositories they reference until the
+/// process exits.
+pub async fn serve(
+    addr: SocketAddr,
+    config: Arc<Config>,
+    github: GitHubApi,
+    data: Arc<Data>,
+    platform: String,
+) -> Fallible<()> {
+    let make_svc = make_service_fn(move |_conn| {
+        let config = config.clone();
+        let github = github.clone();
+        let data = data.clone();
+        let platform = platform.clone();
+
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let config = config.clone();
+                let github = github.clone();
+                let data = data.clone();
+                let platform = platform.clone();
+
+                async move {
+                    Ok::<_, Infallible>(
+                        match handle_delivery(req, &config, &github, &data, &platform).await {
+                            Ok(resp) => resp,
+                            Err(err) => {
+                                warn!("failed to process webhook delivery: {}", err);
+                                Response::builder()
+                                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                                    .body(Body::empty())
+                                    .unwrap()
+                            }
+                        },
+                    )
+                }
+            }))
+        }
+    });
+
+    info!(
+        "listening for GitHub webhook deliveries on http://{} (platform: {})",
+        addr, platform
+    );
+    Server::bind(&addr).serve(make_svc).await?;
+    Ok(())
+}
@@ -0,0 +1,122 @@
+// Copyright (c) 2018 Pietro Albini <pietro@pietroalbini.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is furnished to do
+// so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Observability surface for a long-running scrape: a small HTTP server that
+//! exports Prometheus gauges/counters so progress can be monitored and
+//! alerted on instead of only being visible through log lines.
+
+use crate::prelude::*;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use lazy_static::lazy_static;
+use prometheus::{Encoder, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder};
+use std::net::SocketAddr;
+
+lazy_static! {
+    /// Total repositories stored, labeled by platform.
+    pub static ref REPOS_STORED: IntCounterVec = register_int_counter_vec(
+        "rust_repos_stored_total",
+        "total number of repositories stored",
+        &["platform"],
+    );
+
+    /// Repositories stored with a `Cargo.toml`, labeled by platform.
+    pub static ref REPOS_WITH_CARGO_TOML: IntCounterVec = register_int_counter_vec(
+        "rust_repos_with_cargo_toml_total",
+        "total number of repositories stored with a Cargo.toml",
+        &["platform"],
+    );
+
+    /// Repositories stored with a `Cargo.lock`, labeled by platform.
+    pub static ref REPOS_WITH_CARGO_LOCK: IntCounterVec = register_int_counter_vec(
+        "rust_repos_with_cargo_lock_total",
+        "total number of repositories stored with a Cargo.lock",
+        &["platform"],
+    );
+
+    /// Current scraping cursor, labeled by platform.
+    pub static ref LAST_ID: IntGaugeVec = register_int_gauge_vec(
+        "rust_repos_last_id",
+        "last repository ID scraped, per platform",
+        &["platform"],
+    );
+
+    /// Total calls made to the GitHub API.
+    pub static ref GITHUB_API_CALLS: IntCounterVec = register_int_counter_vec(
+        "rust_repos_github_api_calls_total",
+        "total number of calls made to the GitHub API",
+        &["endpoint"],
+    );
+
+    /// Total retries triggered by `GitHubApi::retry`.
+    pub static ref GITHUB_API_RETRIES: IntCounterVec = register_int_counter_vec(
+        "rust_repos_github_api_retries_total",
+        "total number of retries triggered against the GitHub API",
+        &["endpoint"],
+    );
+
+    /// Requests to the GitHub API currently in flight. Unlabeled because it
+    /// mirrors the single process-global `concurrent_requests` atomic shared
+    /// by every endpoint, not a per-endpoint count.
+    pub static ref GITHUB_CONCURRENT_REQUESTS: IntGauge = register_int_gauge(
+        "rust_repos_github_concurrent_requests",
+        "number of GitHub API requests currently in flight",
+    );
+}
+
+fn register_int_counter_vec(name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+    let metric = IntCounterVec::new(prometheus::Opts::new(name, help), labels)
+        .expect("failed to create metric");
+    prometheus::register(Box::new(metric.clone())).expect("failed to register metric");
+    metric
+}
+
+fn register_int_gauge_vec(name: &str, help: &str, labels: &[&str]) -> IntGaugeVec {
+    let metric = IntGaugeVec::new(prometheus::Opts::new(name, help), labels)
+        .expect("failed to create metric");
+    prometheus::register(Box::new(metric.clone())).expect("failed to register metric");
+    metric
+}
+
+fn register_int_gauge(name: &str, help: &str) -> IntGauge {
+    let metric = IntGauge::new(name, help).expect("failed to create metric");
+    prometheus::register(Box::new(metric.clone())).expect("failed to register metric");
+    metric
+}
+
+async fn serve_metrics(_req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+    let metric_families = prometheus::gather();
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&metric_families, &mut buffer)
+        .expect("failed to encode metrics");
+
+    Ok(Response::new(Body::from(buffer)))
+}
+
+/// Runs the Prometheus exporter on `addr` until the process exits.
+pub async fn serve(addr: SocketAddr) -> Fallible<()> {
+    let make_svc =
+        make_service_fn(|_conn| async { Ok::<_, hyper::Error>(service_fn(serve_metrics)) });
+
+    info!("exporting Prometheus metrics on http://{}/metrics", addr);
+    Server::bind(&addr).serve(make_svc).await?;
+    Ok(())
+}
@@ -0,0 +1,76 @@
+// Copyright (c) 2018 Pietro Albini <pietro@pietroalbini.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is furnished to do
+// so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::config::Config;
+use crate::prelude::*;
+use crate::sink::Sink;
+use async_trait::async_trait;
+use std::path::PathBuf;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+/// Writes objects as plain files under a local directory. This is the
+/// historical behavior, kept as the default so a persistent volume is all
+/// that's needed to run the scraper.
+pub struct LocalSink {
+    base_dir: PathBuf,
+}
+
+impl LocalSink {
+    pub fn new(config: &Config) -> Self {
+        LocalSink {
+            base_dir: config.data_dir.clone(),
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.base_dir.join(key)
+    }
+}
+
+#[async_trait]
+impl Sink for LocalSink {
+    async fn read(&self, key: &str) -> Fallible<Option<Vec<u8>>> {
+        let path = self.path(key);
+        if !fs::try_exists(&path).await? {
+            return Ok(None);
+        }
+        Ok(Some(fs::read(path).await?))
+    }
+
+    async fn write(&self, key: &str, contents: &[u8]) -> Fallible<()> {
+        fs::write(self.path(key), contents).await?;
+        Ok(())
+    }
+
+    async fn append(&self, key: &str, contents: &[u8]) -> Fallible<()> {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(key))
+            .await?;
+        file.write_all(contents).await?;
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Fallible<bool> {
+        Ok(fs::try_exists(self.path(key)).await?)
+    }
+}
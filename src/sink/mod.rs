@@ -0,0 +1,74 @@
+// Copyright (c) 2018 Pietro Albini <pietro@pietroalbini.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is furnished to do
+// so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Where the CSV/state output bytes actually end up.
+//!
+//! [`CsvBackend`](crate::data::CsvBackend) and
+//! [`Data::write_feed`](crate::data::Data::write_feed) used to hardcode a
+//! local `base_dir`. Dispatching through a [`Sink`] instead lets the scraper
+//! run in ephemeral/containerized environments without a persistent local
+//! volume, and lets multiple consumers read the dataset from shared object
+//! storage.
+
+mod local;
+mod s3;
+
+use crate::config::Config;
+use crate::prelude::*;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+pub use self::local::LocalSink;
+pub use self::s3::S3Sink;
+
+/// Which [`Sink`] implementation the scraper's output should be written to.
+/// Selected in `Config`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkKind {
+    Local,
+    S3,
+}
+
+/// A place `<platform>.csv`, `state.json` and `<platform>.atom` can be
+/// written to and read back from, keyed by file name.
+///
+/// Every method is async: the S3 implementation talks to the network, and
+/// every caller (the scraper loop, the webhook handler, `Data::store_repo`)
+/// already runs on a Tokio runtime, so there's no sync context to block from
+/// without risking a "cannot block the current thread" panic.
+#[async_trait]
+pub trait Sink: Send + Sync {
+    /// Reads the whole object named `key`, or `None` if it doesn't exist.
+    async fn read(&self, key: &str) -> Fallible<Option<Vec<u8>>>;
+    /// Overwrites (or creates) the object named `key` with `contents`.
+    async fn write(&self, key: &str, contents: &[u8]) -> Fallible<()>;
+    /// Appends `contents` to the object named `key`, creating it if needed.
+    async fn append(&self, key: &str, contents: &[u8]) -> Fallible<()>;
+    /// Whether an object named `key` currently exists.
+    async fn exists(&self, key: &str) -> Fallible<bool>;
+}
+
+pub fn build(config: &Config) -> Fallible<Arc<dyn Sink>> {
+    Ok(match config.sink_kind {
+        SinkKind::Local => Arc::new(LocalSink::new(config)),
+        SinkKind::S3 => Arc::new(S3Sink::new(config)?),
+    })
+}
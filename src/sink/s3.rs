@@ -0,0 +1,128 @@
+// Copyright (c) 2018 Pietro Albini <pietro@pietroalbini.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is furnished to do
+// so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::config::Config;
+use crate::prelude::*;
+use crate::sink::Sink;
+use async_trait::async_trait;
+use rusoto_core::{ByteStream, Region};
+use rusoto_s3::{GetObjectRequest, HeadObjectRequest, PutObjectRequest, S3Client, S3};
+use tokio::io::AsyncReadExt;
+
+/// Writes objects to an S3-compatible bucket instead of the local disk, so
+/// the scraper can run in ephemeral/containerized environments without a
+/// persistent volume and multiple consumers can read the dataset from
+/// shared storage.
+///
+/// S3 has no native append operation, so [`Sink::append`] reads the current
+/// object back and re-uploads it with `contents` tacked on; this is fine for
+/// the append-mostly CSV/state workload this sink is built for.
+///
+/// Every method here `await`s the `rusoto` client directly instead of
+/// blocking a runtime thread on it: this sink is always driven from async
+/// callers (the scraper loop, the webhook handler, `Data::store_repo`), and
+/// blocking on a handle from inside the runtime that owns it panics.
+pub struct S3Sink {
+    client: S3Client,
+    bucket: String,
+    key_prefix: String,
+}
+
+impl S3Sink {
+    pub fn new(config: &Config) -> Fallible<Self> {
+        let region = Region::Custom {
+            name: config.s3_region.clone(),
+            endpoint: config.s3_endpoint.clone(),
+        };
+
+        Ok(S3Sink {
+            client: S3Client::new(region),
+            bucket: config.s3_bucket.clone(),
+            key_prefix: config.s3_key_prefix.clone(),
+        })
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        format!("{}/{}", self.key_prefix.trim_end_matches('/'), key)
+    }
+}
+
+#[async_trait]
+impl Sink for S3Sink {
+    async fn read(&self, key: &str) -> Fallible<Option<Vec<u8>>> {
+        let request = GetObjectRequest {
+            bucket: self.bucket.clone(),
+            key: self.object_key(key),
+            ..Default::default()
+        };
+
+        match self.client.get_object(request).await {
+            Ok(output) => {
+                let stream = output.body.ok_or_else(|| err_msg("empty S3 object body"))?;
+                let mut buffer = Vec::new();
+                stream.into_async_read().read_to_end(&mut buffer).await?;
+                Ok(Some(buffer))
+            }
+            Err(rusoto_core::RusotoError::Service(rusoto_s3::GetObjectError::NoSuchKey(_))) => {
+                Ok(None)
+            }
+            Err(err) => Err(err_msg(err.to_string())
+                .context("failed to read S3 object")
+                .into()),
+        }
+    }
+
+    async fn write(&self, key: &str, contents: &[u8]) -> Fallible<()> {
+        let request = PutObjectRequest {
+            bucket: self.bucket.clone(),
+            key: self.object_key(key),
+            body: Some(ByteStream::from(contents.to_vec())),
+            ..Default::default()
+        };
+
+        self.client
+            .put_object(request)
+            .await
+            .map_err(|err| err_msg(err.to_string()).context("failed to write S3 object"))?;
+        Ok(())
+    }
+
+    async fn append(&self, key: &str, contents: &[u8]) -> Fallible<()> {
+        let mut buffer = self.read(key).await?.unwrap_or_default();
+        buffer.extend_from_slice(contents);
+        self.write(key, &buffer).await
+    }
+
+    async fn exists(&self, key: &str) -> Fallible<bool> {
+        let request = HeadObjectRequest {
+            bucket: self.bucket.clone(),
+            key: self.object_key(key),
+            ..Default::default()
+        };
+
+        match self.client.head_object(request).await {
+            Ok(_) => Ok(true),
+            Err(rusoto_core::RusotoError::Unknown(resp)) if resp.status == 404 => Ok(false),
+            Err(err) => Err(err_msg(err.to_string())
+                .context("failed to check S3 object")
+                .into()),
+        }
+    }
+}